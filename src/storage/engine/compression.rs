@@ -0,0 +1,194 @@
+use std::io::{self, Read, Write};
+
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use super::{CF, Engine, Error, Modify, Result, Snapshot, WriteOptions};
+
+// Header byte prefixed to every stored value so reads can tell whether the
+// rest of the bytes need inflating.
+const IDENTITY: u8 = 0;
+const DEFLATE: u8 = 1;
+
+/// Wraps any `Engine` and transparently deflates values on write, inflating
+/// them again on read. Keys are left untouched, so ordering and `seek`
+/// semantics of the wrapped engine are unaffected.
+pub struct CompressedEngine {
+    inner: Box<Engine>,
+}
+
+impl CompressedEngine {
+    pub fn new(inner: Box<Engine>) -> CompressedEngine {
+        CompressedEngine { inner: inner }
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn read_varint(data: &[u8]) -> (u64, &[u8]) {
+    let mut n = 0u64;
+    let mut shift = 0;
+    for (i, &byte) in data.iter().enumerate() {
+        n |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return (n, &data[i + 1..]);
+        }
+        shift += 7;
+    }
+    (n, &[])
+}
+
+fn deflate(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::Default);
+    try!(encoder.write_all(raw));
+    encoder.finish()
+}
+
+fn inflate(compressed: &[u8]) -> io::Result<Vec<u8>> {
+    let mut decoder = DeflateDecoder::new(compressed);
+    let mut out = Vec::new();
+    try!(decoder.read_to_end(&mut out));
+    Ok(out)
+}
+
+// Only compress when it actually saves space; otherwise fall back to storing
+// the raw bytes behind an identity header, so small/incompressible values
+// never regress.
+fn encode_value(raw: &[u8]) -> Result<Vec<u8>> {
+    let compressed = try!(deflate(raw).map_err(|e| Error::Other(Box::new(e))));
+    if compressed.len() < raw.len() {
+        let mut out = Vec::with_capacity(1 + 10 + compressed.len());
+        out.push(DEFLATE);
+        write_varint(&mut out, raw.len() as u64);
+        out.extend_from_slice(&compressed);
+        Ok(out)
+    } else {
+        let mut out = Vec::with_capacity(1 + raw.len());
+        out.push(IDENTITY);
+        out.extend_from_slice(raw);
+        Ok(out)
+    }
+}
+
+fn decode_value(stored: &[u8]) -> Result<Vec<u8>> {
+    match stored.split_first() {
+        Some((&IDENTITY, rest)) => Ok(rest.to_vec()),
+        Some((&DEFLATE, rest)) => {
+            let (_raw_len, compressed) = read_varint(rest);
+            inflate(compressed).map_err(|e| Error::Other(Box::new(e)))
+        }
+        Some((other, _)) => Err(Error::Other(format!("unknown value codec {}", other).into())),
+        None => Ok(Vec::new()),
+    }
+}
+
+impl Engine for CompressedEngine {
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match try!(self.inner.get(cf, key)) {
+            Some(v) => decode_value(&v).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        match try!(self.inner.seek(cf, key)) {
+            Some((k, v)) => decode_value(&v).map(|v| Some((k, v))),
+            None => Ok(None),
+        }
+    }
+
+    fn iter<'a>(&'a self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        let inner = try!(self.inner.iter(cf, start, end));
+        Ok(Box::new(inner.map(|item| item.and_then(|(k, v)| decode_value(&v).map(|v| (k, v))))))
+    }
+
+    fn iter_rev<'a>(&'a self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        let inner = try!(self.inner.iter_rev(cf, start, end));
+        Ok(Box::new(inner.map(|item| item.and_then(|(k, v)| decode_value(&v).map(|v| (k, v))))))
+    }
+
+    fn write_opt(&mut self, batch: Vec<Modify>, opts: &WriteOptions) -> Result<()> {
+        // Keep the encoded copies alive for the duration of the inner write,
+        // since `Modify` only borrows its value.
+        let mut buffers = Vec::with_capacity(batch.len());
+        let mut ops = Vec::with_capacity(batch.len());
+        for m in batch {
+            match m {
+                Modify::Delete(cf, k) => ops.push((cf, k, None)),
+                Modify::Put(cf, (k, v)) => {
+                    buffers.push(try!(encode_value(v)));
+                    ops.push((cf, k, Some(buffers.len() - 1)));
+                }
+            }
+        }
+        let new_batch = ops.into_iter()
+            .map(|(cf, k, idx)| match idx {
+                Some(i) => Modify::Put(cf, (k, &buffers[i])),
+                None => Modify::Delete(cf, k),
+            })
+            .collect();
+        self.inner.write_opt(new_batch, opts)
+    }
+
+    fn snapshot<'a>(&'a self) -> Result<Box<Snapshot + 'a>> {
+        let inner = try!(self.inner.snapshot());
+        Ok(Box::new(CompressedSnapshot { inner: inner }))
+    }
+}
+
+struct CompressedSnapshot<'a> {
+    inner: Box<Snapshot + 'a>,
+}
+
+impl<'a> Snapshot for CompressedSnapshot<'a> {
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match try!(self.inner.get(cf, key)) {
+            Some(v) => decode_value(&v).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        match try!(self.inner.seek(cf, key)) {
+            Some((k, v)) => decode_value(&v).map(|v| Some((k, v))),
+            None => Ok(None),
+        }
+    }
+
+    fn iter<'b>(&'b self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'b>> {
+        let inner = try!(self.inner.iter(cf, start, end));
+        Ok(Box::new(inner.map(|item| item.and_then(|(k, v)| decode_value(&v).map(|v| (k, v))))))
+    }
+
+    fn iter_rev<'b>(&'b self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'b>> {
+        let inner = try!(self.inner.iter_rev(cf, start, end));
+        Ok(Box::new(inner.map(|item| item.and_then(|(k, v)| decode_value(&v).map(|v| (k, v))))))
+    }
+}
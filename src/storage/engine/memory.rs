@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::collections::Bound;
+use std::sync::Arc;
+
+use super::{CF, Engine, Modify, Result, Snapshot, WriteOptions};
+
+type Map = Arc<BTreeMap<Vec<u8>, Vec<u8>>>;
+
+struct BTreeStore {
+    default: Map,
+    lock: Map,
+    write: Map,
+}
+
+impl BTreeStore {
+    fn new() -> BTreeStore {
+        BTreeStore {
+            default: Arc::new(BTreeMap::new()),
+            lock: Arc::new(BTreeMap::new()),
+            write: Arc::new(BTreeMap::new()),
+        }
+    }
+
+    fn map(&self, cf: CF) -> &BTreeMap<Vec<u8>, Vec<u8>> {
+        match cf {
+            CF::Default => &self.default,
+            CF::Lock => &self.lock,
+            CF::Write => &self.write,
+        }
+    }
+
+    fn map_mut(&mut self, cf: CF) -> &mut BTreeMap<Vec<u8>, Vec<u8>> {
+        let map = match cf {
+            CF::Default => &mut self.default,
+            CF::Lock => &mut self.lock,
+            CF::Write => &mut self.write,
+        };
+        Arc::make_mut(map)
+    }
+
+    // A cheap, copy-on-write snapshot: later writes call `Arc::make_mut`,
+    // which clones the underlying map the first time it is shared rather
+    // than mutating it in place, so this view never observes them.
+    fn snapshot(&self) -> BTreeStore {
+        BTreeStore {
+            default: self.default.clone(),
+            lock: self.lock.clone(),
+            write: self.write.clone(),
+        }
+    }
+
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.map(cf).get(key).cloned())
+    }
+
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let mut range = self.map(cf).range(Bound::Included(&key.to_vec()), Bound::Unbounded);
+        Ok(range.next().map(|(k, v)| (k.clone(), v.clone())))
+    }
+
+    fn iter<'a>(&'a self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        let range = self.map(cf).range(Bound::Included(&start.to_vec()), Bound::Unbounded);
+        Ok(Box::new(BTreeEngineIterator {
+            inner: range,
+            end: end.map(|e| e.to_vec()),
+            reverse: false,
+        }))
+    }
+
+    fn iter_rev<'a>(&'a self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        let range = self.map(cf)
+            .range(Bound::Unbounded, Bound::Included(&start.to_vec()))
+            .rev();
+        Ok(Box::new(BTreeEngineIterator {
+            inner: range,
+            end: end.map(|e| e.to_vec()),
+            reverse: true,
+        }))
+    }
+}
+
+pub struct BTreeEngine {
+    store: BTreeStore,
+}
+
+impl BTreeEngine {
+    pub fn new() -> BTreeEngine {
+        BTreeEngine { store: BTreeStore::new() }
+    }
+}
+
+pub struct BTreeSnapshot {
+    store: BTreeStore,
+}
+
+struct BTreeEngineIterator<I> {
+    inner: I,
+    end: Option<Vec<u8>>,
+    reverse: bool,
+}
+
+impl<'a, I> Iterator for BTreeEngineIterator<I>
+    where I: Iterator<Item = (&'a Vec<u8>, &'a Vec<u8>)>
+{
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Result<(Vec<u8>, Vec<u8>)>> {
+        match self.inner.next() {
+            Some((k, v)) => {
+                let past_end = match self.end {
+                    Some(ref end) => if self.reverse { k <= end } else { k >= end },
+                    None => false,
+                };
+                if past_end {
+                    None
+                } else {
+                    Some(Ok((k.clone(), v.clone())))
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+impl Engine for BTreeEngine {
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.store.get(cf, key)
+    }
+
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        self.store.seek(cf, key)
+    }
+
+    fn iter<'a>(&'a self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        self.store.iter(cf, start, end)
+    }
+
+    fn iter_rev<'a>(&'a self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        self.store.iter_rev(cf, start, end)
+    }
+
+    fn write_opt(&mut self, batch: Vec<Modify>, _: &WriteOptions) -> Result<()> {
+        // `&mut self` already gives us exclusive access to the maps, so the
+        // whole batch is applied as a single atomic unit with no extra
+        // locking; there is nothing to `fsync` for an in-memory engine, so
+        // `sync` is a no-op here.
+        for rev in batch {
+            match rev {
+                Modify::Delete(cf, k) => {
+                    self.store.map_mut(cf).remove(k);
+                }
+                Modify::Put(cf, (k, v)) => {
+                    self.store.map_mut(cf).insert(k.to_vec(), v.to_vec());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn snapshot<'a>(&'a self) -> Result<Box<Snapshot + 'a>> {
+        Ok(Box::new(BTreeSnapshot { store: self.store.snapshot() }))
+    }
+}
+
+impl Snapshot for BTreeSnapshot {
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.store.get(cf, key)
+    }
+
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        self.store.seek(cf, key)
+    }
+
+    fn iter<'a>(&'a self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        self.store.iter(cf, start, end)
+    }
+
+    fn iter_rev<'a>(&'a self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        self.store.iter_rev(cf, start, end)
+    }
+}
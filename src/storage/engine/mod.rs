@@ -2,34 +2,115 @@ use self::memory::BTreeEngine;
 use std::{error, result};
 use std::fmt::{self, Display, Formatter};
 use self::rocksdb::RocksEngine;
+use self::compression::CompressedEngine;
 
 mod memory;
 mod rocksdb;
+mod compression;
+
+/// Column families used to separate raw data, lock metadata, and
+/// write/MVCC metadata into distinct keyspaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CF {
+    Default,
+    Lock,
+    Write,
+}
+
+impl CF {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            CF::Default => "default",
+            CF::Lock => "lock",
+            CF::Write => "write",
+        }
+    }
+}
+
+pub const ALL_CFS: &'static [CF] = &[CF::Default, CF::Lock, CF::Write];
 
 #[derive(Debug)]
 pub enum Modify<'a> {
-    Delete(&'a [u8]),
-    Put((&'a [u8], &'a [u8])),
+    Delete(CF, &'a [u8]),
+    Put(CF, (&'a [u8], &'a [u8])),
+}
+
+/// Options controlling how a `write` is applied. The default (`sync: false`)
+/// takes the fast, unsynced path; set `sync` for durability-critical commits
+/// that must survive a crash immediately after the call returns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    pub sync: bool,
 }
 
 pub trait Engine {
-    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
-    fn seek(&self, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>>;
-    fn write(&mut self, batch: Vec<Modify>) -> Result<()>;
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>>;
+
+    /// Scan keys in `[start, end)` in ascending order, pulling pairs lazily
+    /// instead of repeatedly reseeking. `end` of `None` scans to the end of
+    /// the keyspace.
+    fn iter<'a>(&'a self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>>;
+
+    /// Like `iter`, but scans in descending order starting at `start` down
+    /// to (but excluding) `end`.
+    fn iter_rev<'a>(&'a self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>>;
+
+    /// Apply `batch` atomically: either every modification lands or none do.
+    /// If the same key is touched more than once, the modifications are
+    /// applied in order and the last one wins.
+    fn write_opt(&mut self, batch: Vec<Modify>, opts: &WriteOptions) -> Result<()>;
+
+    fn write(&mut self, batch: Vec<Modify>) -> Result<()> {
+        self.write_opt(batch, &WriteOptions::default())
+    }
 
     fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
-        self.write(vec![Modify::Put((key, value))])
+        self.write(vec![Modify::Put(CF::Default, (key, value))])
     }
 
     fn delete(&mut self, key: &[u8]) -> Result<()> {
-        self.write(vec![Modify::Delete(key)])
+        self.write(vec![Modify::Delete(CF::Default, key)])
     }
+
+    /// Take a consistent, read-only view of the store. The returned
+    /// `Snapshot` reflects exactly the writes committed before this call and
+    /// none committed after, regardless of concurrent `write`s.
+    fn snapshot<'a>(&'a self) -> Result<Box<Snapshot + 'a>>;
+}
+
+/// The read half of `Engine`, pinned to a single point-in-time view of the
+/// store.
+pub trait Snapshot {
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>>;
+
+    fn iter<'a>(&'a self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>>;
+
+    fn iter_rev<'a>(&'a self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>>;
 }
 
 #[derive(Debug)]
 pub enum Descriptor<'a> {
     Memory,
     RocksDBPath(&'a str),
+    Compressed(Box<Descriptor<'a>>),
 }
 
 pub fn new_engine(desc: Descriptor) -> Result<Box<Engine>> {
@@ -38,6 +119,9 @@ pub fn new_engine(desc: Descriptor) -> Result<Box<Engine>> {
         Descriptor::RocksDBPath(path) => {
             RocksEngine::new(path).map(|engine| -> Box<Engine> { Box::new(engine) })
         }
+        Descriptor::Compressed(inner) => {
+            new_engine(*inner).map(|engine| -> Box<Engine> { Box::new(CompressedEngine::new(engine)) })
+        }
     }
 }
 
@@ -72,13 +156,16 @@ pub type Result<T> = result::Result<T, Error>;
 
 #[cfg(test)]
 mod tests {
-    use super::{Descriptor, Engine, Modify};
+    use super::{CF, Descriptor, Engine, Modify, Snapshot, WriteOptions};
 
     #[test]
     fn memory() {
         let mut e = super::new_engine(Descriptor::Memory).unwrap();
         get_put(&mut *e);
         batch(&mut *e);
+        iter(&mut *e);
+        cf(&mut *e);
+        snapshot(&mut *e);
     }
 
     #[test]
@@ -86,14 +173,38 @@ mod tests {
         let mut e = super::new_engine(Descriptor::RocksDBPath("/tmp/rocks")).unwrap();
         get_put(&mut *e);
         batch(&mut *e);
+        iter(&mut *e);
+        cf(&mut *e);
+        snapshot(&mut *e);
+    }
+
+    #[test]
+    fn compressed() {
+        let desc = Descriptor::Compressed(Box::new(Descriptor::Memory));
+        let mut e = super::new_engine(desc).unwrap();
+        get_put(&mut *e);
+        batch(&mut *e);
+        iter(&mut *e);
+        cf(&mut *e);
+
+        // A large, repetitive value should round-trip through the deflate
+        // path, and a tiny value through the identity fallback.
+        let big = vec![b'a'; 4096];
+        e.put(b"big", &big).unwrap();
+        assert_has(&*e, b"big", &big);
+
+        e.put(b"tiny", b"x").unwrap();
+        assert_has(&*e, b"tiny", b"x");
+
+        snapshot(&mut *e);
     }
 
     fn assert_has(engine: &Engine, key: &[u8], value: &[u8]) {
-        assert_eq!(engine.get(key).unwrap().unwrap(), value);
+        assert_eq!(engine.get(CF::Default, key).unwrap().unwrap(), value);
     }
 
     fn assert_none(engine: &Engine, key: &[u8]) {
-        assert_eq!(engine.get(key).unwrap(), None);
+        assert_eq!(engine.get(CF::Default, key).unwrap(), None);
     }
 
     fn get_put(engine: &mut Engine) {
@@ -107,12 +218,78 @@ mod tests {
     }
 
     fn batch(engine: &mut Engine) {
-        engine.write(vec![Modify::Put((b"x", b"1")), Modify::Put((b"y", b"2"))]).unwrap();
+        engine.write(vec![Modify::Put(CF::Default, (b"x", b"1")),
+                     Modify::Put(CF::Default, (b"y", b"2"))])
+            .unwrap();
         assert_has(engine, b"x", b"1");
         assert_has(engine, b"y", b"2");
 
-        engine.write(vec![Modify::Delete(b"x"), Modify::Delete(b"y")]).unwrap();
+        engine.write(vec![Modify::Delete(CF::Default, b"x"), Modify::Delete(CF::Default, b"y")])
+            .unwrap();
         assert_none(engine, b"y");
         assert_none(engine, b"y");
+
+        // A key touched twice in the same batch follows last-writer-wins.
+        engine.write(vec![Modify::Put(CF::Default, (b"x", b"1")),
+                     Modify::Put(CF::Default, (b"x", b"2"))])
+            .unwrap();
+        assert_has(engine, b"x", b"2");
+
+        engine.write_opt(vec![Modify::Delete(CF::Default, b"x")], &WriteOptions { sync: true })
+            .unwrap();
+        assert_none(engine, b"x");
+    }
+
+    fn iter(engine: &mut Engine) {
+        engine.write(vec![Modify::Put(CF::Default, (b"a", b"1")),
+                     Modify::Put(CF::Default, (b"b", b"2")),
+                     Modify::Put(CF::Default, (b"c", b"3"))])
+            .unwrap();
+
+        let pairs: Vec<_> = engine.iter(CF::Default, b"a", Some(b"c"))
+            .unwrap()
+            .map(|p| p.unwrap())
+            .collect();
+        assert_eq!(pairs,
+                   vec![(b"a".to_vec(), b"1".to_vec()), (b"b".to_vec(), b"2".to_vec())]);
+
+        let rev: Vec<_> = engine.iter_rev(CF::Default, b"c", Some(b"a"))
+            .unwrap()
+            .map(|p| p.unwrap())
+            .collect();
+        assert_eq!(rev,
+                   vec![(b"c".to_vec(), b"3".to_vec()), (b"b".to_vec(), b"2".to_vec())]);
+
+        engine.write(vec![Modify::Delete(CF::Default, b"a"),
+                     Modify::Delete(CF::Default, b"b"),
+                     Modify::Delete(CF::Default, b"c")])
+            .unwrap();
+    }
+
+    fn cf(engine: &mut Engine) {
+        engine.write(vec![Modify::Put(CF::Lock, (b"k", b"lock-value")),
+                     Modify::Put(CF::Write, (b"k", b"write-value"))])
+            .unwrap();
+
+        assert_eq!(engine.get(CF::Lock, b"k").unwrap().unwrap(), b"lock-value");
+        assert_eq!(engine.get(CF::Write, b"k").unwrap().unwrap(), b"write-value");
+        assert_eq!(engine.get(CF::Default, b"k").unwrap(), None);
+
+        engine.write(vec![Modify::Delete(CF::Lock, b"k"), Modify::Delete(CF::Write, b"k")])
+            .unwrap();
+    }
+
+    fn snapshot(engine: &mut Engine) {
+        engine.put(b"k", b"1").unwrap();
+        let snap = engine.snapshot().unwrap();
+        assert_eq!(snap.get(CF::Default, b"k").unwrap().unwrap(), b"1");
+
+        // Writes made after the snapshot was taken must not be visible
+        // through it.
+        engine.put(b"k", b"2").unwrap();
+        assert_eq!(snap.get(CF::Default, b"k").unwrap().unwrap(), b"1");
+        assert_eq!(engine.get(CF::Default, b"k").unwrap().unwrap(), b"2");
+
+        engine.delete(b"k").unwrap();
     }
 }
@@ -0,0 +1,200 @@
+use rocksdb::{DB, Direction, IteratorMode, WriteBatch, Writable};
+use rocksdb::Snapshot as DbSnapshot;
+use rocksdb::WriteOptions as DbWriteOptions;
+
+use super::{ALL_CFS, CF, Engine, Error, Modify, Result, Snapshot, WriteOptions};
+
+pub struct RocksEngine {
+    db: DB,
+}
+
+impl RocksEngine {
+    pub fn new(path: &str) -> Result<RocksEngine> {
+        let cfs: Vec<&str> = ALL_CFS.iter().map(|cf| cf.name()).collect();
+        DB::open_cf(&Default::default(), path, &cfs)
+            .map(|db| RocksEngine { db: db })
+            .map_err(|e| Error::Other(e.into()))
+    }
+
+    fn handle(&self, cf: CF) -> Result<&rocksdb::ColumnFamily> {
+        self.db
+            .cf_handle(cf.name())
+            .ok_or_else(|| Error::Other(format!("column family {} not found", cf.name()).into()))
+    }
+}
+
+struct RocksEngineIterator<'a> {
+    inner: Box<Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>,
+    end: Option<Vec<u8>>,
+    reverse: bool,
+}
+
+impl<'a> Iterator for RocksEngineIterator<'a> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Result<(Vec<u8>, Vec<u8>)>> {
+        match self.inner.next() {
+            Some((k, v)) => {
+                let past_end = match self.end {
+                    Some(ref end) => {
+                        if self.reverse {
+                            &*k <= end.as_slice()
+                        } else {
+                            &*k >= end.as_slice()
+                        }
+                    }
+                    None => false,
+                };
+                if past_end {
+                    None
+                } else {
+                    Some(Ok((k.into_vec(), v.into_vec())))
+                }
+            }
+            None => None,
+        }
+    }
+}
+
+impl Engine for RocksEngine {
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let handle = try!(self.handle(cf));
+        self.db
+            .get_cf(handle, key)
+            .map(|v| v.map(|v| v.to_vec()))
+            .map_err(|e| Error::Other(e.into()))
+    }
+
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let handle = try!(self.handle(cf));
+        let mut iter = try!(self.db
+            .iterator_cf(handle, IteratorMode::From(key, Direction::Forward))
+            .map_err(|e| Error::Other(e.into())));
+        Ok(iter.next().map(|(k, v)| (k.into_vec(), v.into_vec())))
+    }
+
+    fn iter<'a>(&'a self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        let handle = try!(self.handle(cf));
+        let iter = try!(self.db
+            .iterator_cf(handle, IteratorMode::From(start, Direction::Forward))
+            .map_err(|e| Error::Other(e.into())));
+        Ok(Box::new(RocksEngineIterator {
+            inner: Box::new(iter),
+            end: end.map(|e| e.to_vec()),
+            reverse: false,
+        }))
+    }
+
+    fn iter_rev<'a>(&'a self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>> {
+        let handle = try!(self.handle(cf));
+        let iter = try!(self.db
+            .iterator_cf(handle, IteratorMode::From(start, Direction::Reverse))
+            .map_err(|e| Error::Other(e.into())));
+        Ok(Box::new(RocksEngineIterator {
+            inner: Box::new(iter),
+            end: end.map(|e| e.to_vec()),
+            reverse: true,
+        }))
+    }
+
+    fn write_opt(&mut self, batch: Vec<Modify>, opts: &WriteOptions) -> Result<()> {
+        // Route the whole batch through a single `WriteBatch` so RocksDB
+        // applies it atomically instead of key-by-key.
+        let mut wb = WriteBatch::new();
+        for rev in batch {
+            let res = match rev {
+                Modify::Delete(cf, k) => {
+                    let handle = try!(self.handle(cf));
+                    wb.delete_cf(handle, k)
+                }
+                Modify::Put(cf, (k, v)) => {
+                    let handle = try!(self.handle(cf));
+                    wb.put_cf(handle, k, v)
+                }
+            };
+            try!(res.map_err(|e| Error::Other(e.into())));
+        }
+
+        let mut db_opts = DbWriteOptions::new();
+        db_opts.set_sync(opts.sync);
+        self.db.write_opt(wb, &db_opts).map_err(|e| Error::Other(e.into()))
+    }
+
+    fn snapshot<'a>(&'a self) -> Result<Box<Snapshot + 'a>> {
+        Ok(Box::new(RocksSnapshot {
+            db: &self.db,
+            snap: self.db.snapshot(),
+        }))
+    }
+}
+
+pub struct RocksSnapshot<'a> {
+    db: &'a DB,
+    snap: DbSnapshot<'a>,
+}
+
+impl<'a> RocksSnapshot<'a> {
+    fn handle(&self, cf: CF) -> Result<&rocksdb::ColumnFamily> {
+        self.db
+            .cf_handle(cf.name())
+            .ok_or_else(|| Error::Other(format!("column family {} not found", cf.name()).into()))
+    }
+}
+
+impl<'a> Snapshot for RocksSnapshot<'a> {
+    fn get(&self, cf: CF, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let handle = try!(self.handle(cf));
+        self.snap
+            .get_cf(handle, key)
+            .map(|v| v.map(|v| v.to_vec()))
+            .map_err(|e| Error::Other(e.into()))
+    }
+
+    fn seek(&self, cf: CF, key: &[u8]) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let handle = try!(self.handle(cf));
+        let mut iter = try!(self.snap
+            .iterator_cf(handle, IteratorMode::From(key, Direction::Forward))
+            .map_err(|e| Error::Other(e.into())));
+        Ok(iter.next().map(|(k, v)| (k.into_vec(), v.into_vec())))
+    }
+
+    fn iter<'b>(&'b self,
+                cf: CF,
+                start: &[u8],
+                end: Option<&[u8]>)
+                -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'b>> {
+        let handle = try!(self.handle(cf));
+        let iter = try!(self.snap
+            .iterator_cf(handle, IteratorMode::From(start, Direction::Forward))
+            .map_err(|e| Error::Other(e.into())));
+        Ok(Box::new(RocksEngineIterator {
+            inner: Box::new(iter),
+            end: end.map(|e| e.to_vec()),
+            reverse: false,
+        }))
+    }
+
+    fn iter_rev<'b>(&'b self,
+                     cf: CF,
+                     start: &[u8],
+                     end: Option<&[u8]>)
+                     -> Result<Box<Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'b>> {
+        let handle = try!(self.handle(cf));
+        let iter = try!(self.snap
+            .iterator_cf(handle, IteratorMode::From(start, Direction::Reverse))
+            .map_err(|e| Error::Other(e.into())));
+        Ok(Box::new(RocksEngineIterator {
+            inner: Box::new(iter),
+            end: end.map(|e| e.to_vec()),
+            reverse: true,
+        }))
+    }
+}